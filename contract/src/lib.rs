@@ -1,10 +1,12 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Map;
-use near_sdk::json_types::{Base58PublicKey, U128};
+use near_sdk::json_types::{Base58PublicKey, U128, U64};
 use near_sdk::{
-    env, ext_contract, near_bindgen, AccountId, Balance, Promise, PromiseResult, PublicKey,
+    env, ext_contract, near_bindgen, AccountId, Balance, Promise, PromiseOrValue, PromiseResult,
+    PublicKey,
 };
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -12,32 +14,209 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize)]
 pub struct LinkDrop {
-    pub accounts: Map<PublicKey, Balance>,
+    pub accounts: Map<PublicKey, DropState>,
+    /// NEP-141 token balances escrowed for a key, keyed by `(public_key, token_contract_id)`.
+    pub ft_accounts: Map<(PublicKey, AccountId), Balance>,
+    /// Multi-use drops, where a single key can be claimed `uses_remaining` more times.
+    pub multi_use_accounts: Map<PublicKey, MultiUseDrop>,
+    /// NEAR pre-paid via `fund_ft_key_allowance` for a not-yet-seen public key, consumed
+    /// by `ft_on_transfer` to cover the access key it grants on first transfer.
+    /// `ft_transfer_call` carries no attached NEAR, so this has to be funded up front
+    /// instead of being subsidized from the contract's own balance.
+    pub ft_key_allowances: Map<PublicKey, Balance>,
+}
+
+/// A condition tree attached to a drop, borrowed from the shape of Solana's Budget DSL.
+/// Evaluating a `Condition` walks the tree and either resolves to a single payable leaf
+/// or determines that nothing can be paid out yet.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Serialize, Deserialize)]
+pub enum Condition {
+    /// Pays `amount` out, earmarked for the holder of `to_pk`. `to_pk` must equal the
+    /// drop's own public key: `claim`/`claim_balance` only ever look up the signer's own
+    /// key, so a `Pay` earmarked for any other key could never actually be redeemed.
+    /// `send_with_condition` enforces this by rejecting any tree that says otherwise.
+    Pay { amount: Balance, to_pk: PublicKey },
+    /// Only satisfied once `env::block_timestamp()` has reached `timestamp` (nanoseconds).
+    After { timestamp: u64, inner: Box<Condition> },
+    /// Satisfied if either branch resolves; the left branch is preferred when both do.
+    Or(Box<Condition>, Box<Condition>),
+    /// Satisfied only once both branches resolve; pays out the left branch's target.
+    And(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    /// Returns the `(to_pk, amount)` of the first satisfiable `Pay` leaf, treating an
+    /// `After` whose timestamp has not passed as unsatisfied. Returns `None` if nothing
+    /// in the tree can be paid out yet.
+    fn resolve(&self) -> Option<(PublicKey, Balance)> {
+        match self {
+            Condition::Pay { amount, to_pk } => Some((to_pk.clone(), *amount)),
+            Condition::After { timestamp, inner } => {
+                if env::block_timestamp() >= *timestamp {
+                    inner.resolve()
+                } else {
+                    None
+                }
+            }
+            Condition::Or(left, right) => left.resolve().or_else(|| right.resolve()),
+            Condition::And(left, right) => {
+                left.resolve().and_then(|pay| right.resolve().map(|_| pay))
+            }
+        }
+    }
+
+    /// Largest amount this tree could ever pay out across all branches, used to make sure
+    /// a drop is never funded for less than its conditions promise.
+    fn max_payable(&self) -> Balance {
+        match self {
+            Condition::Pay { amount, .. } => *amount,
+            Condition::After { inner, .. } => inner.max_payable(),
+            Condition::Or(left, right) | Condition::And(left, right) => {
+                std::cmp::max(left.max_payable(), right.max_payable())
+            }
+        }
+    }
+
+    /// True only if every `Pay` leaf in this tree is earmarked for `pk`, the one key this
+    /// contract will ever grant access to for this drop.
+    fn all_pay_to(&self, pk: &PublicKey) -> bool {
+        match self {
+            Condition::Pay { to_pk, .. } => to_pk == pk,
+            Condition::After { inner, .. } => inner.all_pay_to(pk),
+            Condition::Or(left, right) | Condition::And(left, right) => {
+                left.all_pay_to(pk) && right.all_pay_to(pk)
+            }
+        }
+    }
+}
+
+/// Balance escrowed for a public key, together with the condition that must resolve
+/// before it can be released via `claim` / `create_account_and_claim`, and the funder
+/// entitled to `refund` it if the key is never claimed.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct DropState {
+    pub balance: Balance,
+    pub condition: Condition,
+    pub funder_id: AccountId,
+    /// Storage staking cost reserved out of the deposit for this entry. It stays with the
+    /// contract when the key is claimed and is sent back to the funder on `refund`.
+    pub storage_cost: Balance,
+}
+
+/// State for a drop whose key can fund more than one account creation/claim. Each claim
+/// pays out `amount_per_use` and decrements `uses_remaining`; the access key is only
+/// deleted once `uses_remaining` reaches zero.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct MultiUseDrop {
+    pub amount_per_use: Balance,
+    pub uses_remaining: u32,
+    pub method_names: Vec<u8>,
+    pub funder_id: AccountId,
 }
 
 /// Access key allowance for linkdrop contract (unlimited)
 const ACCESS_KEY_ALLOWANCE: u128 = 1_000_000_000_000_000_000_000;
 const MAX_ALLOWANCE: u128 = u128::MAX;
 
+/// Flat storage staking cost reserved per stored key, mirroring Orderly's `BASE_COST`.
+/// Deducted from a drop's balance at `send` time; it stays with the contract once the
+/// entry is claimed, and `refund` sends it back to the funder.
+const BASE_COST: Balance = 10_000_000_000_000_000_000;
+
+/// Estimated gas*price cost of the single function call made with a drop's access key.
+/// The rest of `ACCESS_KEY_ALLOWANCE` was never spent and is refunded to the claimant.
+const ESTIMATED_CLAIM_GAS_COST: Balance = 200_000_000_000_000_000_000;
+
 /// Gas attached to the callback from account creation.
 pub const ON_CREATE_ACCOUNT_CALLBACK_GAS: u64 = 40_000_000_000_000;
 
 /// Indicates there are no deposit for a callback for better readability.
 const NO_DEPOSIT: u128 = 0;
 
+/// NEP-141 requires exactly 1 yoctoNEAR attached to transfer-affecting calls.
+const ONE_YOCTO: u128 = 1;
+
+/// Gas attached to the `ft_transfer` call made on a claimed fungible-token drop.
+pub const FT_TRANSFER_GAS: u64 = 10_000_000_000_000;
+
+/// Gas attached to the callback after a fungible-token drop is claimed.
+pub const ON_FT_CLAIMED_CALLBACK_GAS: u64 = 20_000_000_000_000;
+
 // Args for multisig function new
 #[derive(Serialize)]
 pub struct MultisigArgs {
     num_confirmations: u32,
 }
 
+/// View of a drop's recorded balance and the storage cost reserved against it, so
+/// funders can tell exactly how much of their deposit is recoverable.
+#[derive(Serialize)]
+pub struct DropView {
+    pub balance: U128,
+    pub storage_cost: U128,
+}
+
+// Args for the bundled lockup contract's `new`, mirroring the foundation-controlled
+// vesting schedule from the lockup docs (cliff timestamp, release duration, and who can
+// terminate vesting early).
+#[derive(Serialize)]
+pub struct LockupArgs {
+    owner_account_id: AccountId,
+    lockup_timestamp: U64,
+    release_duration: U64,
+    foundation_account_id: AccountId,
+}
+
 #[ext_contract(ext_self)]
 pub trait ExtLinkDrop {
     /// Callback after plain account creation.
     fn on_account_created(&mut self, account_id: AccountId, amount: U128) -> bool;
 
-    /// Callback after creating account and claiming linkdrop.
-    fn on_account_created_and_claimed(&mut self, amount: U128) -> bool;
+    /// Callback after creating account and claiming linkdrop. Carries the drop's original
+    /// `balance` and `condition` so the whole entry can be restored verbatim if account
+    /// creation failed.
+    fn on_account_created_and_claimed(
+        &mut self,
+        balance: U128,
+        condition: Condition,
+        funder_id: AccountId,
+        storage_cost: U128,
+    ) -> bool;
+
+    /// Callback after a fungible-token drop's `ft_transfer` resolves.
+    fn on_ft_claimed(&mut self, public_key: PublicKey, token_id: AccountId, amount: U128) -> bool;
+
+    /// Callback after `create_account_and_claim_ft`'s account creation resolves. On
+    /// success, kicks off the `ft_transfer` (with its own `on_ft_claimed` follow-up); on
+    /// failure, credits the balance back so the key can be claimed again.
+    fn on_account_created_for_ft_claim(
+        &mut self,
+        public_key: PublicKey,
+        token_id: AccountId,
+        amount: U128,
+        new_account_id: AccountId,
+    ) -> PromiseOrValue<bool>;
+
+    /// Callback after `create_account_and_claim_multi_use`'s account creation resolves.
+    fn on_multi_use_account_created(
+        &mut self,
+        amount: U128,
+        exhausted: bool,
+        method_names: Vec<u8>,
+        funder_id: AccountId,
+    ) -> bool;
+}
+
+#[ext_contract(ext_fungible_token)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+/// NEP-141 hook implemented by contracts that want to receive `ft_transfer_call`.
+pub trait FungibleTokenReceiver {
+    /// Called by the token contract after it has credited `amount` of tokens to this
+    /// contract. Returns how much of `amount` should be refunded to `sender_id`.
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
 }
 
 fn is_promise_success() -> bool {
@@ -61,14 +240,69 @@ impl LinkDrop {
     #[payable]
     pub fn send(&mut self, public_key: Base58PublicKey) -> Promise {
         assert!(
-            env::attached_deposit() > ACCESS_KEY_ALLOWANCE,
-            "Attached deposit must be greater than ACCESS_KEY_ALLOWANCE"
+            env::attached_deposit() > ACCESS_KEY_ALLOWANCE + BASE_COST,
+            "Attached deposit must cover ACCESS_KEY_ALLOWANCE and the storage cost"
+        );
+        let pk: PublicKey = public_key.into();
+        let existing = self.accounts.get(&pk);
+        let is_new_entry = existing.is_none();
+        let value = existing.as_ref().map(|drop| drop.balance).unwrap_or(0);
+        let funder_id = existing
+            .map(|drop| drop.funder_id)
+            .unwrap_or_else(env::predecessor_account_id);
+        let storage_cost = if is_new_entry { BASE_COST } else { 0 };
+        let balance = value + env::attached_deposit() - ACCESS_KEY_ALLOWANCE - storage_cost;
+        self.accounts.insert(
+            &pk,
+            &DropState {
+                balance,
+                condition: Condition::Pay {
+                    amount: balance,
+                    to_pk: pk.clone(),
+                },
+                funder_id,
+                storage_cost: BASE_COST,
+            },
+        );
+        Promise::new(env::current_account_id()).add_access_key(
+            pk,
+            ACCESS_KEY_ALLOWANCE,
+            env::current_account_id(),
+            b"claim,create_account_and_claim,create_contract_and_claim".to_vec(),
+        )
+    }
+
+    /// Like `send`, but the released balance is gated by an arbitrary `Condition` tree
+    /// instead of being immediately claimable. The tree must never be able to pay out
+    /// more than the attached deposit minus the access key allowance and `BASE_COST`.
+    #[payable]
+    pub fn send_with_condition(
+        &mut self,
+        public_key: Base58PublicKey,
+        condition: Condition,
+    ) -> Promise {
+        assert!(
+            env::attached_deposit() > ACCESS_KEY_ALLOWANCE + BASE_COST,
+            "Attached deposit must cover ACCESS_KEY_ALLOWANCE and the storage cost"
+        );
+        let balance = env::attached_deposit() - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        assert!(
+            condition.max_payable() <= balance,
+            "Condition tree can pay out more than the attached deposit covers"
+        );
+        let pk: PublicKey = public_key.into();
+        assert!(
+            condition.all_pay_to(&pk),
+            "Every Pay leaf's to_pk must equal the drop's own public key"
         );
-        let pk = public_key.into();
-        let value = self.accounts.get(&pk).unwrap_or(0);
         self.accounts.insert(
             &pk,
-            &(value + env::attached_deposit() - ACCESS_KEY_ALLOWANCE),
+            &DropState {
+                balance,
+                condition,
+                funder_id: env::predecessor_account_id(),
+                storage_cost: BASE_COST,
+            },
         );
         Promise::new(env::current_account_id()).add_access_key(
             pk,
@@ -85,14 +319,29 @@ impl LinkDrop {
         method_names: String,
     ) -> Promise {
         assert!(
-            env::attached_deposit() > ACCESS_KEY_ALLOWANCE,
-            "Attached deposit must be greater than ACCESS_KEY_ALLOWANCE"
+            env::attached_deposit() > ACCESS_KEY_ALLOWANCE + BASE_COST,
+            "Attached deposit must cover ACCESS_KEY_ALLOWANCE and the storage cost"
         );
-        let pk = public_key.into();
-        let value = self.accounts.get(&pk).unwrap_or(0);
+        let pk: PublicKey = public_key.into();
+        let existing = self.accounts.get(&pk);
+        let is_new_entry = existing.is_none();
+        let value = existing.as_ref().map(|drop| drop.balance).unwrap_or(0);
+        let funder_id = existing
+            .map(|drop| drop.funder_id)
+            .unwrap_or_else(env::predecessor_account_id);
+        let storage_cost = if is_new_entry { BASE_COST } else { 0 };
+        let balance = value + env::attached_deposit() - ACCESS_KEY_ALLOWANCE - storage_cost;
         self.accounts.insert(
             &pk,
-            &(value + env::attached_deposit() - ACCESS_KEY_ALLOWANCE),
+            &DropState {
+                balance,
+                condition: Condition::Pay {
+                    amount: balance,
+                    to_pk: pk.clone(),
+                },
+                funder_id,
+                storage_cost: BASE_COST,
+            },
         );
         Promise::new(env::current_account_id()).add_access_key(
             pk,
@@ -102,6 +351,226 @@ impl LinkDrop {
         )
     }
 
+    /// Removes the entry for `public_key` and sends its remaining balance (including the
+    /// reclaimed access key allowance and the reserved storage cost) back to the funder
+    /// that created it. Can only be
+    /// called by that funder, and only while the key has not yet been claimed. For a drop
+    /// with a real condition tree (anything past a bare `Pay`), refund is refused once the
+    /// recipient's own branch has resolved, so the funder can't race a legitimate claim.
+    pub fn refund(&mut self, public_key: Base58PublicKey) -> Promise {
+        let pk: PublicKey = public_key.into();
+        let drop = self.accounts.get(&pk).expect("Unexpected public key");
+        assert_eq!(
+            env::predecessor_account_id(),
+            drop.funder_id,
+            "Only the funder can refund this drop"
+        );
+        if !matches!(drop.condition, Condition::Pay { .. }) {
+            assert!(
+                !matches!(drop.condition.resolve(), Some((to_pk, _)) if to_pk == pk),
+                "Cannot refund once the recipient's branch has resolved"
+            );
+        }
+        self.accounts.remove(&pk);
+        Promise::new(env::current_account_id()).delete_key(pk);
+        Promise::new(drop.funder_id).transfer(drop.balance + ACCESS_KEY_ALLOWANCE + drop.storage_cost)
+    }
+
+    /// Returns the recorded balance and reserved storage cost for `public_key`, for
+    /// wallets/indexers that want to show a drop's value without claiming it.
+    pub fn get_drop(&self, public_key: Base58PublicKey) -> DropView {
+        let pk: PublicKey = public_key.into();
+        let drop = self.accounts.get(&pk).expect("Unexpected public key");
+        DropView {
+            balance: drop.balance.into(),
+            storage_cost: drop.storage_cost.into(),
+        }
+    }
+
+    /// Funds a single access key that can be claimed `uses` separate times, each paying
+    /// out an equal share of the attached deposit minus `ACCESS_KEY_ALLOWANCE`. Useful for event/QR-code campaigns
+    /// where one printed key should onboard many users. Rejects re-sending to a key that
+    /// already has an active multi-use entry: unlike `send`/`send_limited`, folding a new
+    /// deposit into an existing entry would have to silently change `amount_per_use` for
+    /// uses that may already have been claimed at the old rate, so the funder must
+    /// `refund_multi_use` first instead.
+    #[payable]
+    pub fn send_multi_use(
+        &mut self,
+        public_key: Base58PublicKey,
+        uses: u32,
+        method_names: String,
+    ) -> Promise {
+        assert!(uses > 0, "uses must be greater than zero");
+        assert!(
+            env::attached_deposit() > ACCESS_KEY_ALLOWANCE,
+            "Attached deposit must be greater than ACCESS_KEY_ALLOWANCE"
+        );
+        let pk: PublicKey = public_key.into();
+        assert!(
+            self.multi_use_accounts.get(&pk).is_none(),
+            "A multi-use drop already exists for this public key"
+        );
+        let amount_per_use = (env::attached_deposit() - ACCESS_KEY_ALLOWANCE) / uses as u128;
+        let method_names = method_names.as_bytes().to_vec();
+        self.multi_use_accounts.insert(
+            &pk,
+            &MultiUseDrop {
+                amount_per_use,
+                uses_remaining: uses,
+                method_names: method_names.clone(),
+                funder_id: env::predecessor_account_id(),
+            },
+        );
+        Promise::new(env::current_account_id()).add_access_key(
+            pk,
+            ACCESS_KEY_ALLOWANCE,
+            env::current_account_id(),
+            method_names,
+        )
+    }
+
+    /// Removes the entry for `public_key` and sends its remaining unclaimed balance
+    /// (`amount_per_use * uses_remaining`, plus the reclaimed access key allowance) back
+    /// to the funder. Can only be called by that funder. This is the multi-use
+    /// counterpart to `refund`; a partially-claimed drop's remaining uses are otherwise
+    /// stuck with no recovery path.
+    pub fn refund_multi_use(&mut self, public_key: Base58PublicKey) -> Promise {
+        let pk: PublicKey = public_key.into();
+        let drop = self
+            .multi_use_accounts
+            .get(&pk)
+            .expect("Unexpected public key");
+        assert_eq!(
+            env::predecessor_account_id(),
+            drop.funder_id,
+            "Only the funder can refund this drop"
+        );
+        self.multi_use_accounts.remove(&pk);
+        Promise::new(env::current_account_id()).delete_key(pk);
+        Promise::new(drop.funder_id).transfer(
+            drop.amount_per_use * drop.uses_remaining as u128 + ACCESS_KEY_ALLOWANCE,
+        )
+    }
+
+    /// Puts one use back on a multi-use drop, merging into the existing entry if it is
+    /// still present or recreating a single-use entry if the entry was already removed
+    /// when its last use was consumed. Used to roll back a failed `create_account_and_claim_multi_use`.
+    fn restore_multi_use(
+        &mut self,
+        pk: PublicKey,
+        amount_per_use: Balance,
+        method_names: Vec<u8>,
+        funder_id: AccountId,
+    ) {
+        match self.multi_use_accounts.get(&pk) {
+            Some(mut drop) => {
+                drop.uses_remaining += 1;
+                self.multi_use_accounts.insert(&pk, &drop);
+            }
+            None => {
+                self.multi_use_accounts.insert(
+                    &pk,
+                    &MultiUseDrop {
+                        amount_per_use,
+                        uses_remaining: 1,
+                        method_names,
+                        funder_id,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Consumes one use of the signer's multi-use drop, returning the drop (whose
+    /// `amount_per_use` is the amount to pay out) and whether that was the last use (in which case the caller must delete the key).
+    fn consume_multi_use(&mut self) -> (MultiUseDrop, bool) {
+        let pk = env::signer_account_pk();
+        let mut drop = self
+            .multi_use_accounts
+            .get(&pk)
+            .expect("Unexpected public key");
+        drop.uses_remaining -= 1;
+        let exhausted = drop.uses_remaining == 0;
+        if exhausted {
+            self.multi_use_accounts.remove(&pk);
+        } else {
+            self.multi_use_accounts.insert(&pk, &drop);
+        }
+        (drop, exhausted)
+    }
+
+    /// Claim one use of a multi-use drop for the public key this tx is signed with.
+    pub fn claim_multi_use(&mut self, account_id: AccountId) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Claim only can come from this account"
+        );
+        assert!(
+            env::is_valid_account_id(account_id.as_bytes()),
+            "Invalid account id"
+        );
+        let (drop, exhausted) = self.consume_multi_use();
+        if exhausted {
+            Promise::new(env::current_account_id()).delete_key(env::signer_account_pk());
+        }
+        Promise::new(account_id).transfer(drop.amount_per_use)
+    }
+
+    /// Create a new account and claim one use of a multi-use drop to it.
+    pub fn create_account_and_claim_multi_use(
+        &mut self,
+        new_account_id: AccountId,
+        new_public_key: Base58PublicKey,
+    ) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Create account and claim only can come from this account"
+        );
+        assert!(
+            env::is_valid_account_id(new_account_id.as_bytes()),
+            "Invalid account id"
+        );
+        let (drop, exhausted) = self.consume_multi_use();
+        Promise::new(new_account_id)
+            .create_account()
+            .add_full_access_key(new_public_key.into())
+            .transfer(drop.amount_per_use)
+            .then(ext_self::on_multi_use_account_created(
+                drop.amount_per_use.into(),
+                exhausted,
+                drop.method_names,
+                drop.funder_id,
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                ON_CREATE_ACCOUNT_CALLBACK_GAS,
+            ))
+    }
+
+    /// Removes the drop for the signer's access key once its condition resolves in the
+    /// signer's own favor, returning the drop state together with the amount its resolved
+    /// branch actually authorizes. Panics, without consuming the key, if the condition
+    /// tree hasn't resolved yet, or has resolved to a `Pay` leaf earmarked for a key
+    /// other than the signer's.
+    fn claim_balance(&mut self) -> (DropState, Balance) {
+        let pk = env::signer_account_pk();
+        let drop = self.accounts.get(&pk).expect("Unexpected public key");
+        let amount = match drop.condition.resolve() {
+            Some((to_pk, amount)) if to_pk == pk => amount,
+            _ => panic!("Drop conditions are not satisfied yet"),
+        };
+        self.accounts.remove(&pk);
+        (drop, amount)
+    }
+
+    /// Portion of `ACCESS_KEY_ALLOWANCE` that was never spent on gas for the single
+    /// function call a drop's access key was good for, refunded to the claimant.
+    fn unused_allowance() -> Balance {
+        ACCESS_KEY_ALLOWANCE - ESTIMATED_CLAIM_GAS_COST
+    }
+
     /// Claim tokens for specific account that are attached to the public key this tx is signed with.
     pub fn claim(&mut self, account_id: AccountId) -> Promise {
         assert_eq!(
@@ -113,12 +582,9 @@ impl LinkDrop {
             env::is_valid_account_id(account_id.as_bytes()),
             "Invalid account id"
         );
-        let amount = self
-            .accounts
-            .remove(&env::signer_account_pk())
-            .expect("Unexpected public key");
+        let (_drop, amount) = self.claim_balance();
         Promise::new(env::current_account_id()).delete_key(env::signer_account_pk());
-        Promise::new(account_id).transfer(amount)
+        Promise::new(account_id).transfer(amount + Self::unused_allowance())
     }
 
     /// Create new account and and claim tokens to it.
@@ -136,16 +602,16 @@ impl LinkDrop {
             env::is_valid_account_id(new_account_id.as_bytes()),
             "Invalid account id"
         );
-        let amount = self
-            .accounts
-            .remove(&env::signer_account_pk())
-            .expect("Unexpected public key");
+        let (drop, amount) = self.claim_balance();
         Promise::new(new_account_id)
             .create_account()
             .add_full_access_key(new_public_key.into())
-            .transfer(amount)
+            .transfer(amount + Self::unused_allowance())
             .then(ext_self::on_account_created_and_claimed(
-                amount.into(),
+                drop.balance.into(),
+                drop.condition,
+                drop.funder_id,
+                drop.storage_cost.into(),
                 &env::current_account_id(),
                 NO_DEPOSIT,
                 ON_CREATE_ACCOUNT_CALLBACK_GAS,
@@ -172,14 +638,11 @@ impl LinkDrop {
         );
         let multisig_bytes = include_bytes!("../res/multisig.wasm").to_vec();
         let method_names = b"new,add_request,delete_request,execute_request,confirm,get_request,list_request_ids,get_confirmations".to_vec();
-        let amount = self
-            .accounts
-            .remove(&env::signer_account_pk())
-            .expect("Unexpected public key");
+        let (drop, amount) = self.claim_balance();
         // create the account, contract and return the promise
         Promise::new(new_account_id.clone())
             .create_account()
-            .transfer(amount)
+            .transfer(amount + Self::unused_allowance())
             .deploy_contract(multisig_bytes)
             .function_call(
                 b"new".to_vec(),
@@ -195,7 +658,68 @@ impl LinkDrop {
             )
             .then(
                 ext_self::on_account_created_and_claimed(
-                amount.into(),
+                drop.balance.into(),
+                drop.condition,
+                drop.funder_id,
+                drop.storage_cost.into(),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                ON_CREATE_ACCOUNT_CALLBACK_GAS,
+            ))
+    }
+
+    /********************************
+    Creates a lockup
+    ********************************/
+    pub fn create_lockup_and_claim(
+        &mut self,
+        new_account_id: AccountId,
+        new_public_key: Base58PublicKey,
+        lockup_timestamp: U64,
+        release_duration: U64,
+        foundation_account_id: AccountId,
+    ) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Create account and claim only can come from this account"
+        );
+        assert!(
+            env::is_valid_account_id(new_account_id.as_bytes()),
+            "Invalid account id"
+        );
+        let lockup_bytes = include_bytes!("../res/lockup.wasm").to_vec();
+        let method_names =
+            b"stake,unstake,withdraw_from_staking_pool,withdraw_all_from_staking_pool".to_vec();
+        let (drop, amount) = self.claim_balance();
+        // create the account, deploy the lockup and return the promise
+        Promise::new(new_account_id.clone())
+            .create_account()
+            .transfer(amount + Self::unused_allowance())
+            .deploy_contract(lockup_bytes)
+            .function_call(
+                b"new".to_vec(),
+                serde_json::to_vec(&LockupArgs {
+                    owner_account_id: new_account_id.clone(),
+                    lockup_timestamp,
+                    release_duration,
+                    foundation_account_id,
+                })
+                .unwrap(),
+                NO_DEPOSIT,
+                ON_CREATE_ACCOUNT_CALLBACK_GAS,
+            )
+            .add_access_key(
+                new_public_key.into(),
+                MAX_ALLOWANCE.into(), // the lockup itself enforces the release schedule
+                new_account_id,
+                method_names,
+            )
+            .then(ext_self::on_account_created_and_claimed(
+                drop.balance.into(),
+                drop.condition,
+                drop.funder_id,
+                drop.storage_cost.into(),
                 &env::current_account_id(),
                 NO_DEPOSIT,
                 ON_CREATE_ACCOUNT_CALLBACK_GAS,
@@ -220,13 +744,10 @@ impl LinkDrop {
             env::is_valid_account_id(new_account_id.as_bytes()),
             "Invalid account id"
         );
-        let amount = self
-            .accounts
-            .remove(&env::signer_account_pk())
-            .expect("Unexpected public key");
+        let (drop, amount) = self.claim_balance();
         Promise::new(new_account_id.clone())
             .create_account()
-            .transfer(amount)
+            .transfer(amount + Self::unused_allowance())
             .deploy_contract(contract_bytes)
             .add_access_key(
                 new_public_key.into(),
@@ -236,7 +757,10 @@ impl LinkDrop {
             )
             .then(
                 ext_self::on_account_created_and_claimed(
-                amount.into(),
+                drop.balance.into(),
+                drop.condition,
+                drop.funder_id,
+                drop.storage_cost.into(),
                 &env::current_account_id(),
                 NO_DEPOSIT,
                 ON_CREATE_ACCOUNT_CALLBACK_GAS,
@@ -284,7 +808,13 @@ impl LinkDrop {
     }
 
     /// Callback after execution `create_account_and_claim`.
-    pub fn on_account_created_and_claimed(&mut self, amount: U128) -> bool {
+    pub fn on_account_created_and_claimed(
+        &mut self,
+        balance: U128,
+        condition: Condition,
+        funder_id: AccountId,
+        storage_cost: U128,
+    ) -> bool {
         assert_eq!(
             env::predecessor_account_id(),
             env::current_account_id(),
@@ -294,11 +824,260 @@ impl LinkDrop {
         if creation_succeeded {
             Promise::new(env::current_account_id()).delete_key(env::signer_account_pk());
         } else {
-            // In case of failure, put the amount back.
-            self.accounts
-                .insert(&env::signer_account_pk(), &amount.into());
+            // In case of failure, restore the drop exactly as it was before the claim,
+            // condition tree and all, so it can be claimed or refunded again.
+            let pk = env::signer_account_pk();
+            self.accounts.insert(
+                &pk,
+                &DropState {
+                    balance: balance.into(),
+                    condition,
+                    funder_id,
+                    storage_cost: storage_cost.into(),
+                },
+            );
+        }
+        creation_succeeded
+    }
+
+    /// Callback after `create_account_and_claim_multi_use`'s account creation resolves.
+    /// On success, deletes the access key if that was its last use. On failure, puts the
+    /// use back so the key remains claimable.
+    pub fn on_multi_use_account_created(
+        &mut self,
+        amount: U128,
+        exhausted: bool,
+        method_names: Vec<u8>,
+        funder_id: AccountId,
+    ) -> bool {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Callback can only be called from the contract"
+        );
+        let creation_succeeded = is_promise_success();
+        if creation_succeeded {
+            if exhausted {
+                Promise::new(env::current_account_id()).delete_key(env::signer_account_pk());
+            }
+        } else {
+            self.restore_multi_use(env::signer_account_pk(), amount.into(), method_names, funder_id);
+        }
+        creation_succeeded
+    }
+
+    /// Pre-pays the access key allowance for a public key that will shortly receive its
+    /// first `ft_transfer_call` drop. Must be called (and funded with more than
+    /// `ACCESS_KEY_ALLOWANCE`) before that transfer, since `ft_transfer_call` itself
+    /// carries no attached NEAR for `ft_on_transfer` to mint a key with.
+    #[payable]
+    pub fn fund_ft_key_allowance(&mut self, public_key: Base58PublicKey) {
+        assert!(
+            env::attached_deposit() > ACCESS_KEY_ALLOWANCE,
+            "Attached deposit must be greater than ACCESS_KEY_ALLOWANCE"
+        );
+        let pk: PublicKey = public_key.into();
+        let existing = self.ft_key_allowances.get(&pk).unwrap_or(0);
+        self.ft_key_allowances
+            .insert(&pk, &(existing + env::attached_deposit()));
+    }
+
+    /// Claim a fungible-token drop for the public key this tx is signed with, transferring
+    /// it to `account_id`. Mirrors `claim`, but the transfer is a cross-contract
+    /// `ft_transfer` that can fail, so the key is only deleted once it succeeds.
+    pub fn claim_ft(&mut self, token_id: AccountId, account_id: AccountId) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Claim only can come from this account"
+        );
+        assert!(
+            env::is_valid_account_id(account_id.as_bytes()),
+            "Invalid account id"
+        );
+        let pk = env::signer_account_pk();
+        let amount = self
+            .ft_accounts
+            .remove(&(pk.clone(), token_id.clone()))
+            .expect("Unexpected public key");
+        ext_fungible_token::ft_transfer(
+            account_id,
+            amount.into(),
+            None,
+            &token_id,
+            ONE_YOCTO,
+            FT_TRANSFER_GAS,
+        )
+        .then(ext_self::on_ft_claimed(
+            pk,
+            token_id,
+            amount.into(),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            ON_FT_CLAIMED_CALLBACK_GAS,
+        ))
+    }
+
+    /// Create a new account and claim a fungible-token drop to it. The `ft_transfer` is
+    /// only fired once the account creation has actually succeeded, via
+    /// `on_account_created_for_ft_claim` — chaining it onto the creation batch directly
+    /// would fire it unconditionally, even if the account was never created.
+    pub fn create_account_and_claim_ft(
+        &mut self,
+        token_id: AccountId,
+        new_account_id: AccountId,
+        new_public_key: Base58PublicKey,
+    ) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Create account and claim only can come from this account"
+        );
+        assert!(
+            env::is_valid_account_id(new_account_id.as_bytes()),
+            "Invalid account id"
+        );
+        let pk = env::signer_account_pk();
+        let amount = self
+            .ft_accounts
+            .remove(&(pk.clone(), token_id.clone()))
+            .expect("Unexpected public key");
+        Promise::new(new_account_id.clone())
+            .create_account()
+            .add_full_access_key(new_public_key.into())
+            .then(ext_self::on_account_created_for_ft_claim(
+                pk,
+                token_id,
+                amount.into(),
+                new_account_id,
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                ON_CREATE_ACCOUNT_CALLBACK_GAS,
+            ))
+    }
+
+    /// Callback after a fungible-token drop's `ft_transfer` resolves. On success the
+    /// access key is deleted; on failure the balance is credited back so the key can be
+    /// claimed again.
+    pub fn on_ft_claimed(&mut self, public_key: PublicKey, token_id: AccountId, amount: U128) -> bool {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Callback can only be called from the contract"
+        );
+        let claim_succeeded = is_promise_success();
+        if claim_succeeded {
+            Promise::new(env::current_account_id()).delete_key(public_key);
+        } else {
+            let amount: Balance = amount.into();
+            let key = (public_key, token_id);
+            let value = self.ft_accounts.get(&key).unwrap_or(0);
+            self.ft_accounts.insert(&key, &(value + amount));
+        }
+        claim_succeeded
+    }
+
+    /// Callback after `create_account_and_claim_ft`'s account creation resolves. On
+    /// success, kicks off the `ft_transfer` (with its own `on_ft_claimed` follow-up); on
+    /// failure, credits the balance back so the key can be claimed again, mirroring
+    /// `on_account_created_and_claimed`'s rollback for the native-NEAR path.
+    pub fn on_account_created_for_ft_claim(
+        &mut self,
+        public_key: PublicKey,
+        token_id: AccountId,
+        amount: U128,
+        new_account_id: AccountId,
+    ) -> PromiseOrValue<bool> {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Callback can only be called from the contract"
+        );
+        if is_promise_success() {
+            PromiseOrValue::Promise(
+                ext_fungible_token::ft_transfer(
+                    new_account_id,
+                    amount,
+                    None,
+                    &token_id,
+                    ONE_YOCTO,
+                    FT_TRANSFER_GAS,
+                )
+                .then(ext_self::on_ft_claimed(
+                    public_key,
+                    token_id,
+                    amount,
+                    &env::current_account_id(),
+                    NO_DEPOSIT,
+                    ON_FT_CLAIMED_CALLBACK_GAS,
+                )),
+            )
+        } else {
+            let amount: Balance = amount.into();
+            let key = (public_key, token_id);
+            let value = self.ft_accounts.get(&key).unwrap_or(0);
+            self.ft_accounts.insert(&key, &(value + amount));
+            PromiseOrValue::Value(false)
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for LinkDrop {
+    /// Credits the attached `amount` of `token_id` (the caller) to the key encoded in
+    /// `msg`, adding an access key for it when no balance of `token_id` is yet recorded
+    /// for that key. `msg` is either
+    /// just the Base58 public key, or `"<public key>:<comma-separated method names>"`.
+    ///
+    /// Minting an access key for a not-yet-seen key costs `ACCESS_KEY_ALLOWANCE`;
+    /// `ft_transfer_call` carries no attached NEAR to cover that, so the funder must have
+    /// pre-paid it via `fund_ft_key_allowance`. Without that, the transfer is refunded in
+    /// full rather than subsidizing key creation out of the contract's own balance.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        let (pk_str, method_names) = match msg.find(':') {
+            Some(idx) => (&msg[..idx], msg[idx + 1..].as_bytes().to_vec()),
+            None => (
+                msg.as_str(),
+                b"claim_ft,create_account_and_claim_ft".to_vec(),
+            ),
+        };
+        let pk: PublicKey = Base58PublicKey::try_from(pk_str.to_string())
+            .expect("msg must carry a valid Base58 public key")
+            .into();
+
+        // `sender_id` is only used to validate the call came with a real transfer; the
+        // drop itself is funded for whoever ends up holding `pk`'s access key.
+        assert!(!sender_id.is_empty(), "Invalid sender");
+
+        let key = (pk.clone(), token_id.clone());
+        let is_new_key = self.ft_accounts.get(&key).is_none();
+
+        if is_new_key {
+            let allowance = self.ft_key_allowances.remove(&pk).unwrap_or(0);
+            if allowance <= ACCESS_KEY_ALLOWANCE {
+                if allowance > 0 {
+                    self.ft_key_allowances.insert(&pk, &allowance);
+                }
+                return PromiseOrValue::Value(amount);
+            }
+            Promise::new(env::current_account_id()).add_access_key(
+                pk,
+                ACCESS_KEY_ALLOWANCE,
+                env::current_account_id(),
+                method_names,
+            );
         }
-        creation_succeeded
+
+        let amount: Balance = amount.into();
+        let value = self.ft_accounts.get(&key).unwrap_or(0);
+        self.ft_accounts.insert(&key, &(value + amount));
+        PromiseOrValue::Value(U128(0))
     }
 }
 
@@ -362,6 +1141,12 @@ mod tests {
             self
         }
 
+        #[allow(dead_code)]
+        pub fn block_timestamp(mut self, block_timestamp: u64) -> Self {
+            self.context.block_timestamp = block_timestamp;
+            self
+        }
+
         pub fn attached_deposit(mut self, amount: Balance) -> Self {
             self.context.attached_deposit = amount;
             self
@@ -565,8 +1350,411 @@ mod tests {
             .finish());
         contract.send(pk.clone());
         assert_eq!(
-            contract.accounts.get(&pk.into()).unwrap(),
-            deposit + deposit + 1 - 2 * ACCESS_KEY_ALLOWANCE
+            contract.accounts.get(&pk.into()).unwrap().balance,
+            deposit + deposit + 1 - 2 * ACCESS_KEY_ALLOWANCE - BASE_COST
+        );
+    }
+
+    #[test]
+    fn test_refund() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send(pk.clone());
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .account_balance(deposit)
+            .finish());
+        contract.refund(pk.clone());
+        assert!(contract.accounts.get(&pk.into()).is_none());
+    }
+
+    #[test]
+    fn test_get_drop_storage_cost() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send(pk.clone());
+        let view = contract.get_drop(pk);
+        assert_eq!(view.storage_cost, BASE_COST.into());
+        assert_eq!(
+            view.balance,
+            (deposit - ACCESS_KEY_ALLOWANCE - BASE_COST).into()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_refund_wrong_funder() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send(pk.clone());
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .account_balance(deposit)
+            .finish());
+        contract.refund(pk);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_refund_blocked_once_recipient_branch_resolved() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .attached_deposit(deposit)
+            .finish());
+        let balance = deposit - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        let raw_pk: PublicKey = pk.clone().into();
+        contract.send_with_condition(
+            pk.clone(),
+            Condition::After {
+                timestamp: 1_000,
+                inner: Box::new(Condition::Pay {
+                    amount: balance,
+                    to_pk: raw_pk,
+                }),
+            },
+        );
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .account_balance(deposit)
+            .block_timestamp(1_000)
+            .finish());
+        contract.refund(pk);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_send_with_condition_claim_before_timestamp() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        let balance = deposit - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        contract.send_with_condition(
+            pk.clone(),
+            Condition::After {
+                timestamp: 1_000,
+                inner: Box::new(Condition::Pay {
+                    amount: balance,
+                    to_pk: pk.clone().into(),
+                }),
+            },
+        );
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(pk.into())
+            .account_balance(deposit)
+            .block_timestamp(500)
+            .finish());
+        contract.claim(bob());
+    }
+
+    #[test]
+    fn test_send_with_condition_claim_after_timestamp() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        let balance = deposit - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        contract.send_with_condition(
+            pk.clone(),
+            Condition::After {
+                timestamp: 1_000,
+                inner: Box::new(Condition::Pay {
+                    amount: balance,
+                    to_pk: pk.clone().into(),
+                }),
+            },
+        );
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(pk.clone().into())
+            .account_balance(deposit)
+            .block_timestamp(1_000)
+            .finish());
+        contract.claim(bob());
+        assert!(contract.accounts.get(&pk.into()).is_none());
+    }
+
+    #[test]
+    fn test_send_with_condition_or_of_two_after_branches() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        let full = deposit - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        let partial = full / 2;
+        let raw_pk: PublicKey = pk.clone().into();
+        contract.send_with_condition(
+            pk.clone(),
+            Condition::Or(
+                Box::new(Condition::After {
+                    timestamp: 1_000,
+                    inner: Box::new(Condition::Pay {
+                        amount: partial,
+                        to_pk: raw_pk.clone(),
+                    }),
+                }),
+                Box::new(Condition::After {
+                    timestamp: 2_000,
+                    inner: Box::new(Condition::Pay {
+                        amount: full,
+                        to_pk: raw_pk.clone(),
+                    }),
+                }),
+            ),
+        );
+        // Only the earlier (left) branch has resolved; the claim should still succeed.
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(raw_pk.clone())
+            .account_balance(deposit)
+            .block_timestamp(1_500)
+            .finish());
+        contract.claim(bob());
+        assert!(contract.accounts.get(&raw_pk).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_send_with_condition_rejects_foreign_to_pk() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        let balance = deposit - ACCESS_KEY_ALLOWANCE - BASE_COST;
+        let other_pk: PublicKey = vec![9, 9, 9];
+        contract.send_with_condition(
+            pk,
+            Condition::Pay {
+                amount: balance,
+                to_pk: other_pk,
+            },
+        );
+    }
+
+    #[test]
+    fn test_ft_on_transfer_and_claim() {
+        let mut contract = LinkDrop::default();
+        let pk_str = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz".to_string();
+        let pk: Base58PublicKey = pk_str.clone().try_into().unwrap();
+        let token = "token.near".to_string();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .attached_deposit(ACCESS_KEY_ALLOWANCE * 2)
+            .finish());
+        contract.fund_ft_key_allowance(pk.clone());
+
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(token.clone())
+            .finish());
+        contract.ft_on_transfer("alice".to_string(), U128(1_000_000), pk_str);
+        assert_eq!(
+            contract
+                .ft_accounts
+                .get(&(pk.clone().into(), token.clone()))
+                .unwrap(),
+            1_000_000
+        );
+
+        let context = VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(pk.into())
+            .finish();
+        testing_env!(context);
+        contract.claim_ft(token, bob());
+    }
+
+    #[test]
+    fn test_ft_on_transfer_without_prefunded_allowance_refunds() {
+        let mut contract = LinkDrop::default();
+        let pk_str = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz".to_string();
+        let pk: Base58PublicKey = pk_str.clone().try_into().unwrap();
+        let token = "token.near".to_string();
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(token.clone())
+            .finish());
+        let refund = contract.ft_on_transfer("alice".to_string(), U128(1_000_000), pk_str);
+        match refund {
+            PromiseOrValue::Value(amount) => assert_eq!(amount, U128(1_000_000)),
+            _ => panic!("expected the transfer to be refunded immediately"),
+        }
+        assert!(contract.ft_accounts.get(&(pk.into(), token)).is_none());
+    }
+
+    #[test]
+    fn test_send_multi_use_and_claim_twice() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100 + 2;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send_multi_use(pk.clone(), 2, "claim_multi_use".to_string());
+        assert_eq!(
+            contract
+                .multi_use_accounts
+                .get(&pk.clone().into())
+                .unwrap()
+                .uses_remaining,
+            2
+        );
+
+        let context = VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(pk.clone().into())
+            .account_balance(deposit)
+            .finish();
+        testing_env!(context);
+        contract.claim_multi_use(bob());
+        assert_eq!(
+            contract
+                .multi_use_accounts
+                .get(&pk.clone().into())
+                .unwrap()
+                .uses_remaining,
+            1
+        );
+
+        contract.claim_multi_use(bob());
+        assert!(contract.multi_use_accounts.get(&pk.into()).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_send_multi_use_rejects_resend_to_same_key() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100 + 2;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send_multi_use(pk.clone(), 2, "claim_multi_use".to_string());
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .account_balance(deposit)
+            .attached_deposit(deposit)
+            .finish());
+        contract.send_multi_use(pk, 2, "claim_multi_use".to_string());
+    }
+
+    #[test]
+    fn test_refund_multi_use() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        let deposit = ACCESS_KEY_ALLOWANCE * 100 + 2;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send_multi_use(pk.clone(), 2, "claim_multi_use".to_string());
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(bob())
+            .account_balance(deposit)
+            .finish());
+        contract.refund_multi_use(pk.clone());
+        assert!(contract.multi_use_accounts.get(&pk.into()).is_none());
+    }
+
+    #[test]
+    fn test_create_lockup() {
+        let mut contract = LinkDrop::default();
+        let pk: Base58PublicKey = "qSq3LoufLvTCTNGC3LJePMDGrok8dHMQ5A1YD9psbiz"
+            .try_into()
+            .unwrap();
+        // Deposit money to linkdrop contract.
+        let deposit = ACCESS_KEY_ALLOWANCE * 100;
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .attached_deposit(deposit)
+            .finish());
+        contract.send_limited(pk.clone(), "create_lockup_and_claim".to_string());
+        // Now, send new transaction to link drop contract.
+        let context = VMContextBuilder::new()
+            .current_account_id(linkdrop())
+            .predecessor_account_id(linkdrop())
+            .signer_account_pk(pk.into())
+            .account_balance(deposit)
+            .finish();
+        testing_env!(context);
+        let pk2 = "2S87aQ1PM9o6eBcEXnTR5yBAVRTiNmvj8J8ngZ6FzSca"
+            .try_into()
+            .unwrap();
+        contract.create_lockup_and_claim(
+            bob(),
+            pk2,
+            U64(1_893_456_000_000_000_000),
+            U64(31_536_000_000_000_000),
+            "foundation.near".to_string(),
         );
     }
 }